@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::conversion::{to_bool, to_float, to_int, to_timestamp};
+use crate::error::Error;
+use crate::function::StaticFunction;
+use crate::{math, Value};
+
+/// Functions available to every expression unless shadowed by a user
+/// `function`/`const_function` of the same name. Populated from
+/// `conversion`'s `to_*` helpers, `math::builtins`, and `min`/`max`/`len`.
+fn registry() -> &'static HashMap<&'static str, StaticFunction> {
+    static REGISTRY: OnceLock<HashMap<&'static str, StaticFunction>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut map: HashMap<&'static str, StaticFunction> = HashMap::new();
+        map.insert("to_int", to_int);
+        map.insert("to_float", to_float);
+        map.insert("to_bool", to_bool);
+        map.insert("to_timestamp", to_timestamp);
+        map.insert("min", min);
+        map.insert("max", max);
+        map.insert("len", len);
+        for (name, function) in math::builtins() {
+            map.insert(name, function);
+        }
+        map
+    })
+}
+
+/// Look up a built-in by name. Consulted as the last fallback after a
+/// caller's own `Functions`/`ConstFunctions`, so a user registration of the
+/// same name always wins.
+pub(crate) fn lookup(name: &str) -> Option<StaticFunction> {
+    registry().get(name).copied()
+}
+
+fn min(args: Vec<Value>) -> Result<Value, Error> {
+    numeric_fold(args, "min", f64::min)
+}
+
+fn max(args: Vec<Value>) -> Result<Value, Error> {
+    numeric_fold(args, "max", f64::max)
+}
+
+fn numeric_fold(args: Vec<Value>, name: &str, combine: fn(f64, f64) -> f64) -> Result<Value, Error> {
+    let mut args = args.into_iter();
+    let first = args
+        .next()
+        .ok_or_else(|| Error::ArgumentsLess { name: name.to_string(), min: 1, got: 0 })?;
+    let mut acc = as_f64(&first)?;
+    for value in args {
+        acc = combine(acc, as_f64(&value)?);
+    }
+    if acc.is_finite() {
+        Ok(Value::from(acc))
+    } else {
+        Err(Error::Conversion(format!("{} produced a non-finite result ({})", name, acc)))
+    }
+}
+
+fn len(args: Vec<Value>) -> Result<Value, Error> {
+    let value = args
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::ArgumentsLess { name: "len".to_string(), min: 1, got: 0 })?;
+    match value {
+        Value::String(s) => Ok(Value::from(s.chars().count() as i64)),
+        Value::Array(a) => Ok(Value::from(a.len() as i64)),
+        Value::Object(o) => Ok(Value::from(o.len() as i64)),
+        other => Err(Error::Conversion(format!("len expects a string, array, or object, got {:?}", other))),
+    }
+}
+
+fn as_f64(value: &Value) -> Result<f64, Error> {
+    value
+        .as_f64()
+        .ok_or_else(|| Error::Conversion(format!("expected a numeric argument, got {:?}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_returns_smallest_argument() {
+        assert_eq!(min(vec![Value::from(3), Value::from(1), Value::from(2)]).unwrap(), Value::from(1.0));
+    }
+
+    #[test]
+    fn max_returns_largest_argument() {
+        assert_eq!(max(vec![Value::from(3), Value::from(1), Value::from(2)]).unwrap(), Value::from(3.0));
+    }
+
+    #[test]
+    fn len_counts_string_chars_and_collection_entries() {
+        assert_eq!(len(vec![Value::from("hello")]).unwrap(), Value::from(5));
+        assert_eq!(len(vec![Value::from(vec![1, 2, 3])]).unwrap(), Value::from(3));
+    }
+
+    #[test]
+    fn lookup_finds_conversion_and_math_builtins() {
+        assert!(lookup("to_int").is_some());
+        assert!(lookup("sqrt").is_some());
+        assert!(lookup("min").is_some());
+        assert!(lookup("does_not_exist").is_none());
+    }
+}
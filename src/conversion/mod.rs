@@ -0,0 +1,259 @@
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, Utc};
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// Coerces a loosely-typed context value (most often a JSON string) into the
+/// type an operator or function actually expects.
+///
+/// `resolver` pulls most of its context values out of JSON, where everything
+/// may arrive as a string. `Conversion` is the single place that knows how to
+/// turn such a string (or an already-typed value) into the `bool`/numeric/time
+/// representation the rest of the expression needs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conversion {
+    /// Pass the value through unchanged.
+    Bytes,
+    /// Coerce to a JSON integer.
+    Integer,
+    /// Coerce to a JSON float.
+    Float,
+    /// Coerce to a JSON boolean.
+    Boolean,
+    /// Coerce an RFC 3339 timestamp string to a unix epoch (seconds).
+    Timestamp,
+    /// Coerce a timestamp string using the given chrono strftime pattern,
+    /// interpreted in the local timezone.
+    TimestampFmt(String),
+    /// Coerce a timestamp string using the given chrono strftime pattern,
+    /// where the pattern itself carries a timezone offset.
+    TimestampTZFmt(String),
+}
+
+impl FromStr for Conversion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Conversion, Error> {
+        match s {
+            "asis" | "bytes" | "string" => Ok(Conversion::Bytes),
+            "int" | "integer" => Ok(Conversion::Integer),
+            "float" => Ok(Conversion::Float),
+            "bool" | "boolean" => Ok(Conversion::Boolean),
+            "timestamp" => Ok(Conversion::Timestamp),
+            _ => Err(Error::Conversion(format!("unknown conversion: {}", s))),
+        }
+    }
+}
+
+impl Conversion {
+    /// Convert `value` according to this conversion, returning
+    /// `Error::Conversion` when the value cannot be coerced.
+    pub fn convert(&self, value: Value) -> Result<Value, Error> {
+        match self {
+            Conversion::Bytes => Ok(value),
+            Conversion::Integer => convert_integer(value),
+            Conversion::Float => convert_float(value),
+            Conversion::Boolean => convert_boolean(value),
+            Conversion::Timestamp => convert_timestamp(value),
+            Conversion::TimestampFmt(fmt) => convert_timestamp_fmt(value, fmt),
+            Conversion::TimestampTZFmt(fmt) => convert_timestamp_tz_fmt(value, fmt),
+        }
+    }
+}
+
+fn convert_integer(value: Value) -> Result<Value, Error> {
+    match value {
+        Value::Number(n) if n.is_i64() || n.is_u64() => Ok(Value::Number(n)),
+        Value::Number(n) => Ok(Value::from(n.as_f64().unwrap_or(0.0) as i64)),
+        Value::String(s) => s
+            .trim()
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|e| Error::Conversion(format!("cannot convert {:?} to integer: {}", s, e))),
+        Value::Bool(b) => Ok(Value::from(b as i64)),
+        other => Err(Error::Conversion(format!("cannot convert {:?} to integer", other))),
+    }
+}
+
+fn convert_float(value: Value) -> Result<Value, Error> {
+    match value {
+        Value::Number(n) => Ok(Value::from(n.as_f64().unwrap_or(0.0))),
+        Value::String(s) => s
+            .trim()
+            .parse::<f64>()
+            .map(Value::from)
+            .map_err(|e| Error::Conversion(format!("cannot convert {:?} to float: {}", s, e))),
+        other => Err(Error::Conversion(format!("cannot convert {:?} to float", other))),
+    }
+}
+
+fn convert_boolean(value: Value) -> Result<Value, Error> {
+    match value {
+        Value::Bool(b) => Ok(Value::Bool(b)),
+        Value::String(s) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" => Ok(Value::Bool(true)),
+            "false" | "0" | "no" => Ok(Value::Bool(false)),
+            _ => Err(Error::Conversion(format!("cannot convert {:?} to boolean", s))),
+        },
+        Value::Number(n) => Ok(Value::Bool(n.as_f64().unwrap_or(0.0) != 0.0)),
+        other => Err(Error::Conversion(format!("cannot convert {:?} to boolean", other))),
+    }
+}
+
+fn convert_timestamp(value: Value) -> Result<Value, Error> {
+    match value {
+        Value::Number(n) => Ok(Value::Number(n)),
+        Value::String(s) => DateTime::parse_from_rfc3339(s.trim())
+            .map(|dt| Value::from(dt.with_timezone(&Utc).timestamp()))
+            .map_err(|e| Error::Conversion(format!("cannot convert {:?} to timestamp: {}", s, e))),
+        other => Err(Error::Conversion(format!("cannot convert {:?} to timestamp", other))),
+    }
+}
+
+fn convert_timestamp_fmt(value: Value, fmt: &str) -> Result<Value, Error> {
+    let s = value_as_str(&value)?;
+    chrono::NaiveDateTime::parse_from_str(s.trim(), fmt)
+        .map_err(|e| Error::Conversion(format!("cannot parse {:?} with format {:?}: {}", s, fmt, e)))
+        .and_then(|naive| {
+            naive
+                .and_local_timezone(Local)
+                .single()
+                .ok_or_else(|| Error::Conversion(format!("ambiguous local timestamp {:?}", s)))
+        })
+        .map(|dt| Value::from(dt.with_timezone(&Utc).timestamp()))
+}
+
+fn convert_timestamp_tz_fmt(value: Value, fmt: &str) -> Result<Value, Error> {
+    let s = value_as_str(&value)?;
+    DateTime::parse_from_str(s.trim(), fmt)
+        .map(|dt| Value::from(dt.with_timezone(&Utc).timestamp()))
+        .map_err(|e| Error::Conversion(format!("cannot parse {:?} with format {:?}: {}", s, fmt, e)))
+}
+
+fn value_as_str(value: &Value) -> Result<&str, Error> {
+    value
+        .as_str()
+        .ok_or_else(|| Error::Conversion(format!("expected a string timestamp, got {:?}", value)))
+}
+
+/// Built-in const function: `to_int(x)`.
+pub fn to_int(args: Vec<Value>) -> Result<Value, Error> {
+    let value = args.into_iter().next().unwrap_or(Value::Null);
+    Conversion::Integer.convert(value)
+}
+
+/// Built-in const function: `to_float(x)`.
+pub fn to_float(args: Vec<Value>) -> Result<Value, Error> {
+    let value = args.into_iter().next().unwrap_or(Value::Null);
+    Conversion::Float.convert(value)
+}
+
+/// Built-in const function: `to_bool(x)`.
+pub fn to_bool(args: Vec<Value>) -> Result<Value, Error> {
+    let value = args.into_iter().next().unwrap_or(Value::Null);
+    Conversion::Boolean.convert(value)
+}
+
+/// Built-in const function: `to_timestamp(x, fmt)`. `fmt` is optional; when
+/// omitted, `x` is parsed as RFC 3339.
+pub fn to_timestamp(args: Vec<Value>) -> Result<Value, Error> {
+    let mut args = args.into_iter();
+    let value = args.next().unwrap_or(Value::Null);
+    match args.next().and_then(|v| v.as_str().map(String::from)) {
+        Some(fmt) => Conversion::TimestampFmt(fmt).convert(value),
+        None => Conversion::Timestamp.convert(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_recognizes_every_named_conversion() {
+        assert_eq!(Conversion::from_str("asis").unwrap(), Conversion::Bytes);
+        assert_eq!(Conversion::from_str("bytes").unwrap(), Conversion::Bytes);
+        assert_eq!(Conversion::from_str("string").unwrap(), Conversion::Bytes);
+        assert_eq!(Conversion::from_str("int").unwrap(), Conversion::Integer);
+        assert_eq!(Conversion::from_str("integer").unwrap(), Conversion::Integer);
+        assert_eq!(Conversion::from_str("float").unwrap(), Conversion::Float);
+        assert_eq!(Conversion::from_str("bool").unwrap(), Conversion::Boolean);
+        assert_eq!(Conversion::from_str("boolean").unwrap(), Conversion::Boolean);
+        assert_eq!(Conversion::from_str("timestamp").unwrap(), Conversion::Timestamp);
+    }
+
+    #[test]
+    fn from_str_rejects_an_unknown_name() {
+        let err = Conversion::from_str("not-a-conversion").unwrap_err();
+        assert_eq!(err, Error::Conversion("unknown conversion: not-a-conversion".to_string()));
+    }
+
+    #[test]
+    fn bytes_passes_the_value_through_unchanged() {
+        assert_eq!(Conversion::Bytes.convert(Value::from("hello")).unwrap(), Value::from("hello"));
+    }
+
+    #[test]
+    fn integer_converts_strings_numbers_and_bools() {
+        assert_eq!(Conversion::Integer.convert(Value::from("42")).unwrap(), Value::from(42));
+        assert_eq!(Conversion::Integer.convert(Value::from(3.9)).unwrap(), Value::from(3));
+        assert_eq!(Conversion::Integer.convert(Value::from(true)).unwrap(), Value::from(1));
+        assert!(Conversion::Integer.convert(Value::from("not a number")).is_err());
+        assert!(Conversion::Integer.convert(Value::Null).is_err());
+    }
+
+    #[test]
+    fn float_converts_strings_and_numbers() {
+        assert_eq!(Conversion::Float.convert(Value::from("1.5")).unwrap(), Value::from(1.5));
+        assert_eq!(Conversion::Float.convert(Value::from(2)).unwrap(), Value::from(2.0));
+        assert!(Conversion::Float.convert(Value::from("not a number")).is_err());
+    }
+
+    #[test]
+    fn boolean_converts_strings_and_numbers() {
+        assert_eq!(Conversion::Boolean.convert(Value::from("yes")).unwrap(), Value::Bool(true));
+        assert_eq!(Conversion::Boolean.convert(Value::from("0")).unwrap(), Value::Bool(false));
+        assert_eq!(Conversion::Boolean.convert(Value::from(5)).unwrap(), Value::Bool(true));
+        assert!(Conversion::Boolean.convert(Value::from("maybe")).is_err());
+    }
+
+    #[test]
+    fn timestamp_parses_rfc3339_and_passes_through_numbers() {
+        let converted = Conversion::Timestamp.convert(Value::from("2024-01-02T03:04:05Z")).unwrap();
+        assert_eq!(converted, Value::from(1704164645));
+        assert_eq!(Conversion::Timestamp.convert(Value::from(1704164645)).unwrap(), Value::from(1704164645));
+        assert!(Conversion::Timestamp.convert(Value::from("not a timestamp")).is_err());
+    }
+
+    #[test]
+    fn timestamp_fmt_parses_a_local_strftime_pattern() {
+        let fmt = "%Y-%m-%d %H:%M:%S";
+        let expected = chrono::NaiveDateTime::parse_from_str("2024-01-02 03:04:05", fmt)
+            .unwrap()
+            .and_local_timezone(Local)
+            .unwrap()
+            .with_timezone(&Utc)
+            .timestamp();
+
+        let converted = Conversion::TimestampFmt(fmt.to_string())
+            .convert(Value::from("2024-01-02 03:04:05"))
+            .unwrap();
+        assert_eq!(converted, Value::from(expected));
+
+        assert!(Conversion::TimestampFmt(fmt.to_string()).convert(Value::from("not a timestamp")).is_err());
+    }
+
+    #[test]
+    fn timestamp_tz_fmt_parses_a_pattern_carrying_its_own_offset() {
+        let fmt = "%Y-%m-%d %H:%M:%S %z";
+        let converted = Conversion::TimestampTZFmt(fmt.to_string())
+            .convert(Value::from("2024-01-02 03:04:05 +0000"))
+            .unwrap();
+        assert_eq!(converted, Value::from(1704164645));
+
+        assert!(Conversion::TimestampTZFmt(fmt.to_string()).convert(Value::from("not a timestamp")).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+
+use std::fmt;
+
+/// All the ways building, compiling, or executing an `Expr` can fail.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The expression text could not be parsed.
+    Parse(String),
+    /// A referenced identifier has no matching context value.
+    UnknownIdentifier(String),
+    /// A called name has no matching function, const function, or built-in.
+    UnknownFunction(String),
+    /// A value could not be coerced to the type an operator or `Conversion` expected.
+    Conversion(String),
+    /// A function was called with fewer arguments than its configured `min_args`.
+    ArgumentsLess {
+        name: String,
+        min: usize,
+        got: usize,
+    },
+    /// A function was called with more arguments than its configured `max_args`.
+    ArgumentsGreater {
+        name: String,
+        max: usize,
+        got: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(msg) => write!(f, "parse error: {}", msg),
+            Error::UnknownIdentifier(name) => write!(f, "unknown identifier: {}", name),
+            Error::UnknownFunction(name) => write!(f, "unknown function: {}", name),
+            Error::Conversion(msg) => write!(f, "conversion error: {}", msg),
+            Error::ArgumentsLess { name, min, got } => write!(
+                f,
+                "function {:?} expects at least {} argument(s), got {}",
+                name, min, got
+            ),
+            Error::ArgumentsGreater { name, max, got } => write!(
+                f,
+                "function {:?} expects at most {} argument(s), got {}",
+                name, max, got
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
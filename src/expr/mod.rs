@@ -1,199 +1,520 @@
-
-use serde::{
-    Serialize,
-    Serializer,
-    Deserialize,
-    Deserializer,
-};
-
-use std::cell::RefCell;
-use std::rc::Rc;
-use std::{fmt, cmp};
-
-use crate::function::{StaticFunction, ConstFunction};
-use crate::tree::Tree;
-use crate::error::Error;
-use crate::{to_value, ConstFunctions};
-use crate::{Function, Functions, Context, Contexts, Compiled, Value};
-
-/// Expression builder
-pub struct Expr {
-    expression: String,
-    compiled: Option<Compiled>,
-    functions: Functions,
-    const_functions: Rc<RefCell<ConstFunctions>>,
-    contexts: Contexts,
-}
-
-impl Expr {
-    /// Create an expression.
-    pub fn new<T: Into<String>>(expr: T) -> Expr {
-        Expr {
-            expression: expr.into(),
-            compiled: None,
-            functions: Functions::new(),
-            const_functions: Rc::from(RefCell::from(ConstFunctions::new())),
-            contexts: create_empty_contexts(),
-        }
-    }
-
-    /// Set function. This functions NOT be cloned. Have highest priority.
-    pub fn function<T, F>(mut self, name: T, function: F) -> Expr
-        where T: Into<String>,
-              F: 'static + Fn(Vec<Value>) -> Result<Value, Error> + Sync + Send
-    {
-        self.functions.insert(name.into(), Function::new(function));
-        self
-    }
-
-    /// Set const function. This functions be cloned. Have lowest priority. 
-    pub fn const_function<T>(self, name: T, function: StaticFunction)->Expr
-    where T: Into<String>{
-        self.const_functions.borrow_mut().insert(name.into(), ConstFunction::new(function));
-        self
-    }
-
-    /// Set value.
-    pub fn value<T, V>(mut self, name: T, value: V) -> Expr
-        where T: Into<String>,
-              V: Serialize
-    {
-        self.contexts.last_mut().unwrap().insert(name.into(), to_value(value));
-        self
-    }
-
-    /// Compile an expression.
-    /// An expression can be compiled only once and then invoked multiple times with different context and function.
-    /// You can also execute a expression without compile.
-    pub fn compile(mut self) -> Result<Expr, Error> {
-        self.compiled = Some(Tree::new(self.expression.clone()).compile()?);
-        Ok(self)
-    }
-
-    /// Execute the expression.
-    pub fn exec(&mut self) -> Result<Value, Error> {
-        if self.compiled.is_none() {
-            Tree::new(self.expression.clone()).compile()?(&self.contexts, &self.functions, Rc::clone(&self.const_functions))
-        } else {
-            self.compiled.as_ref().unwrap()(&self.contexts, &self.functions, Rc::clone(&self.const_functions))
-        }
-    }
-
-    /// Get reference to compiled object
-    pub fn get_compiled(&self) -> Option<&Compiled> {
-        self.compiled.as_ref()
-    }
-}
-
-impl Clone for Expr {
-    /// Returns a copy of the value. Notice that functions can not be cloned. The cloned expr's functions will be empty.
-    fn clone(&self) -> Expr {
-        Expr {
-            expression: self.expression.clone(),
-            compiled: if self.compiled.is_some() {
-                Some(Tree::new(self.expression.clone()).compile().unwrap())
-            } else {
-                None
-            },
-            contexts: self.contexts.clone(),
-            functions: Functions::new(),
-            const_functions: Rc::clone(&self.const_functions)
-        }
-    }
-}
-
-impl fmt::Debug for Expr {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(formatter, "{:?}", self.expression)
-    }
-}
-
-impl cmp::PartialEq for Expr {
-    fn eq(&self, other: &Expr) -> bool {
-        self.expression == other.expression
-    }
-}
-
-impl Serialize for Expr {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where
-            S: Serializer,
-    {
-        serializer.serialize_str(format!("{:?}", self).as_str())
-    }
-}
-
-impl<'de> Deserialize<'de> for Expr {
-    fn deserialize<D>(deserializer: D) -> Result<Expr, D::Error>
-        where
-            D: Deserializer<'de>,
-    {
-        String::deserialize(deserializer)
-            .and_then(|expr| Expr::new(expr).compile().map_err(serde::de::Error::custom))
-    }
-}
-
-
-/// Execute options
-pub struct ExecOptions<'a> {
-    expr: &'a Expr,
-    contexts: Option<&'a [Context]>,
-    functions: Option<&'a Functions>,
-    const_functions:  Rc<RefCell<ConstFunctions>>
-}
-
-impl<'a> ExecOptions<'a> {
-    /// Create an option.
-    pub fn new(expr: &'a Expr) -> ExecOptions<'a> {
-        let cf = Rc::clone(&expr.const_functions);
-        ExecOptions {
-            expr,
-            contexts: None,
-            functions: None,
-            const_functions: cf
-        }
-    }
-
-    /// Set contexts.
-    pub fn contexts(&mut self, contexts: &'a [Context]) -> &'a mut ExecOptions {
-        self.contexts = Some(contexts);
-        self
-    }
-
-    /// Set functions.
-    pub fn functions(&mut self, functions: &'a Functions) -> &'a mut ExecOptions {
-        self.functions = Some(functions);
-        self
-    }
-
-    /// Execute the compiled expression.
-    pub fn exec(&self) -> Result<Value, Error> {
-        let empty_contexts = create_empty_contexts();
-        let empty_functions = Functions::new();
-
-        let contexts = if self.contexts.is_some() {
-            self.contexts.unwrap()
-        } else {
-            &empty_contexts
-        };
-
-        let functions = if self.functions.is_some() {
-            self.functions.unwrap()
-        } else {
-            &empty_functions
-        };
-
-        let compiled = self.expr.get_compiled();
-        if let Some (c) = compiled {
-            (c)(contexts, functions, Rc::clone(&self.const_functions))
-        } else {
-            Tree::new(self.expr.expression.clone()).compile()?(contexts, functions,Rc::clone(&self.const_functions))
-        }
-    }
-}
-
-
-fn create_empty_contexts() -> Contexts {
-    let contexts = vec![Context::new()];
-    contexts
+
+use serde::{
+    Serialize,
+    Serializer,
+    Deserialize,
+    Deserializer,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+use std::rc::Rc;
+use std::{fmt, cmp};
+
+use crate::function::{StaticFunction, ConstFunction, AsyncFunction, AsyncFunctionFuture, AsyncFunctions};
+use crate::tree::Tree;
+use crate::error::Error;
+use crate::{to_value, ConstFunctions};
+use crate::{Function, Functions, Context, Contexts, Compiled, Value};
+
+/// Selects the arithmetic `+ - * / %` use when evaluating an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumericMode {
+    /// Ordinary `f64` arithmetic (the default).
+    Float,
+    /// Exact arithmetic over `Rational` values, avoiding float drift for
+    /// expressions like `1/3 + 1/6`.
+    Rational,
+}
+
+/// Expression builder
+pub struct Expr {
+    expression: String,
+    compiled: Option<Compiled>,
+    functions: Functions,
+    const_functions: Rc<RefCell<ConstFunctions>>,
+    contexts: Contexts,
+    numeric_mode: NumericMode,
+    async_functions: AsyncFunctions,
+    memoize: bool,
+    cache: Rc<RefCell<HashMap<u64, CacheEntry>>>,
+}
+
+/// A memoized result, keyed by `fingerprint_contexts`'s hash but verified
+/// against the actual `(identifier, value)` pairs it was computed from
+/// before being trusted — two different inputs hashing to the same `u64`
+/// must not return each other's cached `Value`.
+type CacheEntry = (Vec<(String, Value)>, Value);
+
+impl Expr {
+    /// Create an expression.
+    pub fn new<T: Into<String>>(expr: T) -> Expr {
+        Expr {
+            expression: expr.into(),
+            compiled: None,
+            functions: Functions::new(),
+            const_functions: Rc::from(RefCell::from(ConstFunctions::new())),
+            contexts: create_empty_contexts(),
+            numeric_mode: NumericMode::Float,
+            async_functions: AsyncFunctions::new(),
+            memoize: false,
+            cache: Rc::from(RefCell::from(HashMap::new())),
+        }
+    }
+
+    /// Enable or disable memoization. When enabled, `exec`/`ExecOptions::exec`
+    /// fingerprint only the context values referenced by the expression (via
+    /// `referenced_identifiers`) and skip recomputation when that fingerprint
+    /// has already been seen. Defaults to `false`.
+    pub fn with_memoization(mut self, memoize: bool) -> Expr {
+        self.memoize = memoize;
+        self
+    }
+
+    /// Drop all memoized results.
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    /// Collect the context values for every identifier this expression reads,
+    /// so unrelated context changes don't invalidate the cache.
+    fn fingerprint(&self) -> Vec<(String, Value)> {
+        fingerprint_contexts(&self.referenced_identifiers(), &self.contexts)
+    }
+
+    /// Select the arithmetic used by `+ - * / %`. Defaults to `NumericMode::Float`.
+    pub fn numeric_mode(mut self, numeric_mode: NumericMode) -> Expr {
+        self.numeric_mode = numeric_mode;
+        self
+    }
+
+    /// Get the configured numeric mode.
+    pub fn get_numeric_mode(&self) -> NumericMode {
+        self.numeric_mode
+    }
+
+    /// Set function. This functions NOT be cloned. Have highest priority.
+    pub fn function<T, F>(mut self, name: T, function: F) -> Expr
+        where T: Into<String>,
+              F: 'static + Fn(Vec<Value>) -> Result<Value, Error> + Sync + Send
+    {
+        self.functions.insert(name.into(), Function::new(function));
+        self
+    }
+
+    /// Set const function. This functions be cloned. Have lower priority than
+    /// `function`, but higher than the built-in functions.
+    pub fn const_function<T>(self, name: T, function: StaticFunction)->Expr
+    where T: Into<String>{
+        self.const_functions.borrow_mut().insert(name.into(), ConstFunction::new(function));
+        self
+    }
+
+    /// Set function with an explicit `min`/`max` argument count. `exec` will
+    /// reject calls outside that range with `Error::ArgumentsLess`/`Error::ArgumentsGreater`
+    /// instead of invoking `function`.
+    pub fn function_with_arity<T, F>(mut self, name: T, min: Option<usize>, max: Option<usize>, function: F) -> Expr
+        where T: Into<String>,
+              F: 'static + Fn(Vec<Value>) -> Result<Value, Error> + Sync + Send
+    {
+        self.functions.insert(name.into(), Function::new(function).with_arity(min, max));
+        self
+    }
+
+    /// Set const function with an explicit `min`/`max` argument count. See
+    /// `function_with_arity`.
+    pub fn const_function_with_arity<T>(self, name: T, min: Option<usize>, max: Option<usize>, function: StaticFunction) -> Expr
+        where T: Into<String>
+    {
+        self.const_functions.borrow_mut().insert(name.into(), ConstFunction::new(function).with_arity(min, max));
+        self
+    }
+
+    /// Set an async function. Only `exec_async`/`ExecOptions::exec_async` can
+    /// invoke it; pure sub-expressions elsewhere in the tree still evaluate
+    /// synchronously.
+    pub fn async_function<T, F>(mut self, name: T, function: F) -> Expr
+        where T: Into<String>,
+              F: 'static + Fn(Vec<Value>) -> AsyncFunctionFuture + Sync + Send
+    {
+        self.async_functions.insert(name.into(), AsyncFunction::new(function));
+        self
+    }
+
+    /// Set value.
+    pub fn value<T, V>(mut self, name: T, value: V) -> Expr
+        where T: Into<String>,
+              V: Serialize
+    {
+        self.contexts.last_mut().unwrap().insert(name.into(), to_value(value));
+        self
+    }
+
+    /// Compile an expression.
+    /// An expression can be compiled only once and then invoked multiple times with different context and function.
+    /// You can also execute a expression without compile.
+    pub fn compile(mut self) -> Result<Expr, Error> {
+        self.compiled = Some(Tree::new(self.expression.clone()).compile()?);
+        Ok(self)
+    }
+
+    /// Execute the expression.
+    pub fn exec(&mut self) -> Result<Value, Error> {
+        if !self.memoize {
+            return self.exec_uncached();
+        }
+
+        let pairs = self.fingerprint();
+        let hash = hash_pairs(&pairs);
+        if let Some((cached_pairs, value)) = self.cache.borrow().get(&hash) {
+            if *cached_pairs == pairs {
+                return Ok(value.clone());
+            }
+        }
+
+        let value = self.exec_uncached()?;
+        self.cache.borrow_mut().insert(hash, (pairs, value.clone()));
+        Ok(value)
+    }
+
+    fn exec_uncached(&mut self) -> Result<Value, Error> {
+        exec_with(self, &self.contexts, &self.functions, Rc::clone(&self.const_functions))
+    }
+
+    /// Get reference to compiled object
+    pub fn get_compiled(&self) -> Option<&Compiled> {
+        self.compiled.as_ref()
+    }
+
+    /// Execute the expression, awaiting any async-function node. Ordinary
+    /// operators and sync/const functions still resolve inline; only
+    /// sub-expressions whose tree contains an async call are awaited.
+    pub async fn exec_async(&mut self) -> Result<Value, Error> {
+        Tree::new(self.expression.clone())
+            .exec_async(&self.contexts, &self.functions, Rc::clone(&self.const_functions), &self.async_functions, self.numeric_mode)
+            .await
+    }
+
+    /// List every variable/context key this expression reads, parsing the
+    /// expression afresh on each call (whether or not `compile` has run). Lets a host validate
+    /// that an expression only touches an allowlisted set of context keys
+    /// before ever calling `exec`. Returns an empty list if the expression
+    /// fails to compile.
+    pub fn referenced_identifiers(&self) -> Vec<String> {
+        self.with_tree(Tree::identifiers)
+    }
+
+    /// List every function name this expression invokes, parsing the
+    /// expression afresh on each call (whether or not `compile` has run). Returns an empty list
+    /// if the expression fails to compile.
+    pub fn called_functions(&self) -> Vec<String> {
+        self.with_tree(Tree::function_calls)
+    }
+
+    /// Compile a fresh `Tree` for introspection purposes and run `f` over it.
+    /// The internal node representation is never handed back to the caller;
+    /// only the owned/cloned data `f` derives from it.
+    fn with_tree<F>(&self, f: F) -> Vec<String>
+        where F: Fn(&Tree) -> Vec<String>
+    {
+        Tree::new(self.expression.clone())
+            .compile_tree()
+            .map(|tree| f(&tree))
+            .unwrap_or_default()
+    }
+}
+
+impl Clone for Expr {
+    /// Returns a copy of the value. Notice that functions can not be cloned. The cloned expr's functions will be empty.
+    fn clone(&self) -> Expr {
+        Expr {
+            expression: self.expression.clone(),
+            compiled: if self.compiled.is_some() {
+                Some(Tree::new(self.expression.clone()).compile().unwrap())
+            } else {
+                None
+            },
+            contexts: self.contexts.clone(),
+            functions: Functions::new(),
+            const_functions: Rc::clone(&self.const_functions),
+            numeric_mode: self.numeric_mode,
+            async_functions: AsyncFunctions::new(),
+            memoize: self.memoize,
+            cache: Rc::from(RefCell::from(HashMap::new())),
+        }
+    }
+}
+
+impl fmt::Debug for Expr {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{:?}", self.expression)
+    }
+}
+
+impl cmp::PartialEq for Expr {
+    fn eq(&self, other: &Expr) -> bool {
+        self.expression == other.expression
+    }
+}
+
+impl Serialize for Expr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+    {
+        serializer.serialize_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Expr {
+    fn deserialize<D>(deserializer: D) -> Result<Expr, D::Error>
+        where
+            D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)
+            .and_then(|expr| Expr::new(expr).compile().map_err(serde::de::Error::custom))
+    }
+}
+
+
+/// Execute options
+pub struct ExecOptions<'a> {
+    expr: &'a Expr,
+    contexts: Option<&'a [Context]>,
+    functions: Option<&'a Functions>,
+    const_functions:  Rc<RefCell<ConstFunctions>>,
+    async_functions: Option<&'a AsyncFunctions>,
+}
+
+impl<'a> ExecOptions<'a> {
+    /// Create an option.
+    pub fn new(expr: &'a Expr) -> ExecOptions<'a> {
+        let cf = Rc::clone(&expr.const_functions);
+        ExecOptions {
+            expr,
+            contexts: None,
+            functions: None,
+            const_functions: cf,
+            async_functions: None,
+        }
+    }
+
+    /// Set contexts.
+    pub fn contexts(&mut self, contexts: &'a [Context]) -> &'a mut ExecOptions<'_> {
+        self.contexts = Some(contexts);
+        self
+    }
+
+    /// Set functions.
+    pub fn functions(&mut self, functions: &'a Functions) -> &'a mut ExecOptions<'_> {
+        self.functions = Some(functions);
+        self
+    }
+
+    /// Set async functions.
+    pub fn async_functions(&mut self, async_functions: &'a AsyncFunctions) -> &'a mut ExecOptions<'_> {
+        self.async_functions = Some(async_functions);
+        self
+    }
+
+    /// Execute the compiled expression.
+    pub fn exec(&self) -> Result<Value, Error> {
+        let empty_contexts = create_empty_contexts();
+        let empty_functions = Functions::new();
+
+        let contexts = self.contexts.unwrap_or(&empty_contexts);
+        let functions = self.functions.unwrap_or(&empty_functions);
+
+        if !self.expr.memoize {
+            return exec_with(self.expr, contexts, functions, Rc::clone(&self.const_functions));
+        }
+
+        let pairs = fingerprint_contexts(&self.expr.referenced_identifiers(), contexts);
+        let hash = hash_pairs(&pairs);
+        if let Some((cached_pairs, value)) = self.expr.cache.borrow().get(&hash) {
+            if *cached_pairs == pairs {
+                return Ok(value.clone());
+            }
+        }
+
+        let value = exec_with(self.expr, contexts, functions, Rc::clone(&self.const_functions))?;
+        self.expr.cache.borrow_mut().insert(hash, (pairs, value.clone()));
+        Ok(value)
+    }
+
+    /// Execute the compiled expression, awaiting any async-function node.
+    pub async fn exec_async(&self) -> Result<Value, Error> {
+        let empty_contexts = create_empty_contexts();
+        let empty_functions = Functions::new();
+        let empty_async_functions = AsyncFunctions::new();
+
+        let contexts = self.contexts.unwrap_or(&empty_contexts);
+        let functions = self.functions.unwrap_or(&empty_functions);
+        let async_functions = self.async_functions.unwrap_or(&empty_async_functions);
+
+        Tree::new(self.expr.expression.clone())
+            .exec_async(contexts, functions, Rc::clone(&self.const_functions), async_functions, self.expr.numeric_mode)
+            .await
+    }
+}
+
+
+fn create_empty_contexts() -> Contexts {
+    let contexts = vec![Context::new()];
+    contexts
+}
+
+fn exec_with(expr: &Expr, contexts: &[Context], functions: &Functions, const_functions: Rc<RefCell<ConstFunctions>>) -> Result<Value, Error> {
+    let compiled = expr.get_compiled();
+    if let Some(c) = compiled {
+        (c)(contexts, functions, const_functions, expr.numeric_mode)
+    } else {
+        Tree::new(expr.expression.clone()).compile()?(contexts, functions, const_functions, expr.numeric_mode)
+    }
+}
+
+/// Collect the context value for every identifier in `identifiers`, so
+/// irrelevant context changes don't invalidate a memoized `Value`. Kept
+/// alongside its hash (`hash_pairs`) rather than just the hash, so a cache
+/// hit can be verified against the actual values instead of trusting a
+/// `u64` that a hash collision could share with a different input.
+fn fingerprint_contexts(identifiers: &[String], contexts: &[Context]) -> Vec<(String, Value)> {
+    identifiers
+        .iter()
+        .filter_map(|identifier| {
+            contexts
+                .iter()
+                .rev()
+                .find_map(|context| context.get(identifier))
+                .map(|value| (identifier.clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Hash the fingerprinted `(identifier, value)` pairs into a cache bucket
+/// key. Only a cheap way to index the cache: a matching hash is not by
+/// itself proof of a cache hit, see `fingerprint_contexts`.
+fn hash_pairs(pairs: &[(String, Value)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (identifier, value) in pairs {
+        identifier.hash(&mut hasher);
+        value.to_string().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_numeric_mode_is_float() {
+        let mut expr = Expr::new("1 / 3 + 1 / 6").compile().unwrap();
+        assert_eq!(expr.get_numeric_mode(), NumericMode::Float);
+        assert_eq!(expr.exec().unwrap(), Value::from(0.5));
+    }
+
+    #[test]
+    fn builtin_conversion_and_math_functions_are_available_by_default() {
+        let mut expr = Expr::new("to_int(port) > 1024")
+            .value("port", "8080")
+            .compile()
+            .unwrap();
+        assert_eq!(expr.exec().unwrap(), Value::Bool(true));
+
+        let mut expr = Expr::new("sqrt(16) == 4.0").compile().unwrap();
+        assert_eq!(expr.exec().unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn rational_numeric_mode_is_exact() {
+        let mut expr = Expr::new("a + b")
+            .numeric_mode(NumericMode::Rational)
+            .value("a", "1/3")
+            .value("b", "1/6")
+            .compile()
+            .unwrap();
+        assert_eq!(expr.exec().unwrap(), Value::String("1/2".to_string()));
+    }
+
+    #[test]
+    fn memoization_reuses_cached_value_for_identical_inputs() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_clone = Arc::clone(&calls);
+        let mut expr = Expr::new("double(a)")
+            .with_memoization(true)
+            .function("double", move |args| {
+                calls_clone.fetch_add(1, Ordering::SeqCst);
+                Ok(Value::from(args[0].as_f64().unwrap() * 2.0))
+            })
+            .value("a", 3)
+            .compile()
+            .unwrap();
+
+        assert_eq!(expr.exec().unwrap(), Value::from(6.0));
+        assert_eq!(expr.exec().unwrap(), Value::from(6.0));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn fingerprint_changes_when_a_referenced_value_changes() {
+        let mut first = Context::new();
+        first.insert("a".to_string(), Value::from(1));
+        let mut second = Context::new();
+        second.insert("a".to_string(), Value::from(2));
+
+        let identifiers = vec!["a".to_string()];
+        let pairs_first = fingerprint_contexts(&identifiers, &[first]);
+        let pairs_second = fingerprint_contexts(&identifiers, &[second]);
+
+        assert_ne!(pairs_first, pairs_second);
+        assert_ne!(hash_pairs(&pairs_first), hash_pairs(&pairs_second));
+    }
+
+    #[test]
+    fn exec_async_awaits_async_calls_but_evaluates_pure_subtrees_synchronously() {
+        let mut expr = Expr::new("1 + fetch(a)")
+            .value("a", 41)
+            .async_function("fetch", |args| {
+                Box::pin(async move { Ok(Value::from(args[0].as_f64().unwrap() + 1.0)) })
+            });
+
+        let result = futures::executor::block_on(expr.exec_async()).unwrap();
+        assert_eq!(result, Value::from(43.0));
+    }
+
+    #[test]
+    fn exec_async_falls_back_to_sync_eval_when_no_async_call_is_present() {
+        let mut expr = Expr::new("1 + 2").async_function("fetch", |_args| {
+            Box::pin(async move { Ok(Value::Null) })
+        });
+
+        let result = futures::executor::block_on(expr.exec_async()).unwrap();
+        assert_eq!(result, Value::from(3.0));
+    }
+
+    #[test]
+    fn cache_lookup_rejects_a_stale_value_under_a_colliding_hash() {
+        // Simulate a hash collision directly: two different inputs that land in
+        // the same bucket must not let the first's cached `Value` answer for the
+        // second — the stored pairs, not just the hash, decide a cache hit.
+        let stale_pairs = vec![("a".to_string(), Value::from(1))];
+        let fresh_pairs = vec![("a".to_string(), Value::from(2))];
+        let shared_hash = 42u64;
+
+        let mut cache: HashMap<u64, CacheEntry> = HashMap::new();
+        cache.insert(shared_hash, (stale_pairs, Value::from("stale")));
+
+        let hit = cache
+            .get(&shared_hash)
+            .filter(|(cached_pairs, _)| *cached_pairs == fresh_pairs)
+            .map(|(_, value)| value.clone());
+
+        assert_eq!(hit, None);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,63 @@
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::function::check_arity;
+
+/// A future returned by an `AsyncFunction`'s closure.
+pub type AsyncFunctionFuture = Pin<Box<dyn Future<Output = Result<Value, Error>> + Send>>;
+
+/// Custom function that performs I/O (HTTP lookups, DB queries, ...) before
+/// producing a `Value`. Evaluated from `Expr::exec_async`/`ExecOptions::exec_async`;
+/// ordinary `exec` never calls these and fails to resolve a node that needs one.
+pub struct AsyncFunction {
+    /// Maximum number of arguments.
+    pub max_args: Option<usize>,
+    /// Minimum number of arguments.
+    pub min_args: Option<usize>,
+    /// Accept values and return a future which resolves to a value.
+    pub compiled: Box<dyn Fn(Vec<Value>) -> AsyncFunctionFuture + Sync + Send>,
+}
+
+impl AsyncFunction {
+    /// Create an async function with a closure.
+    pub fn new<F>(closure: F) -> Self
+        where F: 'static + Fn(Vec<Value>) -> AsyncFunctionFuture + Sync + Send
+    {
+        AsyncFunction {
+            max_args: None,
+            min_args: None,
+            compiled: Box::new(closure),
+        }
+    }
+
+    /// Set the minimum and maximum number of arguments this function accepts.
+    pub fn with_arity(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+        self.min_args = min;
+        self.max_args = max;
+        self
+    }
+
+    /// Check `args` against the configured arity, returning a named
+    /// `Error::ArgumentsLess`/`Error::ArgumentsGreater` on mismatch.
+    pub fn check_arity(&self, name: &str, args: &[Value]) -> Result<(), Error> {
+        check_arity(name, self.min_args, self.max_args, args)
+    }
+}
+
+impl fmt::Debug for AsyncFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "AsyncFunction {{ max_args: {:?}, min_args: {:?} }}",
+               self.max_args,
+               self.min_args)
+    }
+}
+
+/// A set of registered async functions, keyed by name.
+pub type AsyncFunctions = HashMap<String, AsyncFunction>;
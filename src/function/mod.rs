@@ -4,6 +4,9 @@ use serde_json::Value;
 
 use crate::error::Error;
 
+mod async_function;
+pub use async_function::{AsyncFunction, AsyncFunctionFuture, AsyncFunctions};
+
 
 /// Custom function
 pub struct Function {
@@ -26,6 +29,19 @@ impl Function {
             compiled: Box::new(closure),
         }
     }
+
+    /// Set the minimum and maximum number of arguments this function accepts.
+    pub fn with_arity(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+        self.min_args = min;
+        self.max_args = max;
+        self
+    }
+
+    /// Check `args` against the configured arity, returning a named
+    /// `Error::ArgumentsLess`/`Error::ArgumentsGreater` on mismatch.
+    pub fn check_arity(&self, name: &str, args: &[Value]) -> Result<(), Error> {
+        check_arity(name, self.min_args, self.max_args, args)
+    }
 }
 
 impl fmt::Debug for Function {
@@ -60,6 +76,19 @@ impl ConstFunction {
             compiled: closure,
         }
     }
+
+    /// Set the minimum and maximum number of arguments this function accepts.
+    pub fn with_arity(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+        self.min_args = min;
+        self.max_args = max;
+        self
+    }
+
+    /// Check `args` against the configured arity, returning a named
+    /// `Error::ArgumentsLess`/`Error::ArgumentsGreater` on mismatch.
+    pub fn check_arity(&self, name: &str, args: &[Value]) -> Result<(), Error> {
+        check_arity(name, self.min_args, self.max_args, args)
+    }
 }
 
 impl fmt::Debug for ConstFunction {
@@ -71,3 +100,54 @@ impl fmt::Debug for ConstFunction {
     }
 }
 
+/// Shared arity check used by both `Function` and `ConstFunction`.
+fn check_arity(name: &str, min: Option<usize>, max: Option<usize>, args: &[Value]) -> Result<(), Error> {
+    if let Some(min) = min {
+        if args.len() < min {
+            return Err(Error::ArgumentsLess { name: name.to_string(), min, got: args.len() });
+        }
+    }
+    if let Some(max) = max {
+        if args.len() > max {
+            return Err(Error::ArgumentsGreater { name: name.to_string(), max, got: args.len() });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn function_rejects_too_few_arguments() {
+        let function = Function::new(|args| Ok(Value::from(args.len() as i64))).with_arity(Some(2), Some(3));
+        let err = function.check_arity("f", &[Value::from(1)]).unwrap_err();
+        assert_eq!(err, Error::ArgumentsLess { name: "f".to_string(), min: 2, got: 1 });
+    }
+
+    #[test]
+    fn function_rejects_too_many_arguments() {
+        let function = Function::new(|args| Ok(Value::from(args.len() as i64))).with_arity(Some(0), Some(1));
+        let err = function.check_arity("f", &[Value::from(1), Value::from(2)]).unwrap_err();
+        assert_eq!(err, Error::ArgumentsGreater { name: "f".to_string(), max: 1, got: 2 });
+    }
+
+    #[test]
+    fn function_accepts_arity_within_bounds() {
+        let function = Function::new(|args| Ok(Value::from(args.len() as i64))).with_arity(Some(1), Some(2));
+        assert!(function.check_arity("f", &[Value::from(1)]).is_ok());
+    }
+
+    #[test]
+    fn const_function_enforces_arity() {
+        fn double(args: Vec<Value>) -> Result<Value, Error> {
+            Ok(Value::from(args[0].as_f64().unwrap() * 2.0))
+        }
+        let function = ConstFunction::new(double).with_arity(Some(1), Some(1));
+        assert!(function.check_arity("double", &[]).is_err());
+        assert!(function.check_arity("double", &[Value::from(1), Value::from(2)]).is_err());
+        assert!(function.check_arity("double", &[Value::from(1)]).is_ok());
+    }
+}
+
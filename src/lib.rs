@@ -0,0 +1,37 @@
+
+//! `resolver` compiles small expression strings (`"a + b > 10"`) against
+//! user-supplied context values and functions.
+
+mod builtins;
+pub mod conversion;
+pub mod error;
+pub mod function;
+pub mod expr;
+pub mod math;
+pub mod rational;
+mod tree;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub use error::Error;
+pub use expr::{Expr, ExecOptions, NumericMode};
+pub use function::{AsyncFunction, AsyncFunctionFuture, AsyncFunctions, ConstFunction, Function, StaticFunction};
+pub use serde_json::Value;
+
+/// A single scope of named values available to an expression.
+pub type Context = HashMap<String, Value>;
+/// A stack of scopes. Later entries take precedence over earlier ones.
+pub type Contexts = Vec<Context>;
+/// User-registered functions, keyed by name.
+pub type Functions = HashMap<String, Function>;
+/// User-registered const functions, keyed by name.
+pub type ConstFunctions = HashMap<String, ConstFunction>;
+/// A compiled expression, ready to be invoked against contexts/functions.
+pub type Compiled = Box<dyn Fn(&[Context], &Functions, Rc<RefCell<ConstFunctions>>, NumericMode) -> Result<Value, Error>>;
+
+/// Convert any serializable value into the `Value` representation used in contexts.
+pub fn to_value<T: serde::Serialize>(value: T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
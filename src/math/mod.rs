@@ -0,0 +1,122 @@
+
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// Built-in math functions, registered by default alongside `min`/`max`/`len`.
+///
+/// Each entry is a `(name, function)` pair suitable for `Expr::const_function`.
+pub fn builtins() -> Vec<(&'static str, crate::function::StaticFunction)> {
+    vec![
+        ("sin", sin),
+        ("cos", cos),
+        ("tan", tan),
+        ("asin", asin),
+        ("acos", acos),
+        ("atan", atan),
+        ("atan2", atan2),
+        ("sqrt", sqrt),
+        ("cbrt", cbrt),
+        ("exp", exp),
+        ("ln", ln),
+        ("log", log),
+        ("log2", log2),
+        ("pow", pow),
+        ("abs", abs),
+        ("floor", floor),
+        ("ceil", ceil),
+        ("round", round),
+        ("signum", signum),
+        ("pi", pi),
+        ("e", e),
+    ]
+}
+
+fn arg(args: &[Value], index: usize) -> Result<f64, Error> {
+    args.get(index)
+        .and_then(Value::as_f64)
+        .ok_or_else(|| Error::Conversion(format!("expected a numeric argument at position {}", index)))
+}
+
+/// Reject `NaN`/infinite results instead of letting `Value::from(f64)` coerce
+/// them to `Value::Null` (e.g. `sqrt(-1)`, `ln(-1)`, `asin(2)`), which would
+/// otherwise make a domain error evaluate to a silently-wrong `null`.
+fn finite(n: f64) -> Result<Value, Error> {
+    if n.is_finite() {
+        Ok(Value::from(n))
+    } else {
+        Err(Error::Conversion(format!("operation produced a non-finite result ({})", n)))
+    }
+}
+
+macro_rules! unary {
+    ($name:ident, $op:expr) => {
+        fn $name(args: Vec<Value>) -> Result<Value, Error> {
+            let x = arg(&args, 0)?;
+            let f: fn(f64) -> f64 = $op;
+            finite(f(x))
+        }
+    };
+}
+
+unary!(sin, f64::sin);
+unary!(cos, f64::cos);
+unary!(tan, f64::tan);
+unary!(asin, f64::asin);
+unary!(acos, f64::acos);
+unary!(atan, f64::atan);
+unary!(sqrt, f64::sqrt);
+unary!(cbrt, f64::cbrt);
+unary!(exp, f64::exp);
+unary!(ln, f64::ln);
+unary!(log2, f64::log2);
+unary!(abs, f64::abs);
+unary!(floor, f64::floor);
+unary!(ceil, f64::ceil);
+unary!(round, f64::round);
+unary!(signum, f64::signum);
+
+fn atan2(args: Vec<Value>) -> Result<Value, Error> {
+    finite(arg(&args, 0)?.atan2(arg(&args, 1)?))
+}
+
+fn pow(args: Vec<Value>) -> Result<Value, Error> {
+    finite(arg(&args, 0)?.powf(arg(&args, 1)?))
+}
+
+fn log(args: Vec<Value>) -> Result<Value, Error> {
+    finite(arg(&args, 0)?.log(arg(&args, 1)?))
+}
+
+fn pi(_args: Vec<Value>) -> Result<Value, Error> {
+    Ok(Value::from(std::f64::consts::PI))
+}
+
+fn e(_args: Vec<Value>) -> Result<Value, Error> {
+    Ok(Value::from(std::f64::consts::E))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqrt_of_a_negative_number_errors_instead_of_returning_null() {
+        assert!(sqrt(vec![Value::from(-1)]).is_err());
+    }
+
+    #[test]
+    fn ln_of_a_negative_number_errors_instead_of_returning_null() {
+        assert!(ln(vec![Value::from(-1)]).is_err());
+    }
+
+    #[test]
+    fn asin_outside_its_domain_errors_instead_of_returning_null() {
+        assert!(asin(vec![Value::from(2)]).is_err());
+    }
+
+    #[test]
+    fn sqrt_of_a_non_negative_number_succeeds() {
+        assert_eq!(sqrt(vec![Value::from(16)]).unwrap(), Value::from(4.0));
+    }
+}
@@ -0,0 +1,79 @@
+
+use std::str::FromStr;
+
+use num_rational::BigRational;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// An exact rational number, used when `Expr`'s `NumericMode` is set to
+/// `Rational` so that `+ - * / %` don't suffer float drift (e.g. `1/3 + 1/6`).
+///
+/// Serializes to/from its canonical `"numerator/denominator"` string so it
+/// round-trips through `to_value`/serde like any other `Value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rational(pub BigRational);
+
+impl Rational {
+    pub fn new(numerator: i64, denominator: i64) -> Rational {
+        Rational(BigRational::new(numerator.into(), denominator.into()))
+    }
+
+    /// Encode as a `Value::String` in `"numerator/denominator"` form.
+    pub fn to_value(&self) -> Value {
+        Value::String(format!("{}/{}", self.0.numer(), self.0.denom()))
+    }
+
+    /// Decode a `Value` produced by `to_value`, or a bare integer `Value`.
+    /// Float and other `Value`s are rejected with `Error::Conversion`.
+    pub fn from_value(value: &Value) -> Result<Rational, Error> {
+        match value {
+            Value::String(s) => Rational::from_str(s),
+            Value::Number(n) if n.is_i64() => Ok(Rational::new(n.as_i64().unwrap(), 1)),
+            other => Err(Error::Conversion(format!("cannot convert {:?} to a rational", other))),
+        }
+    }
+}
+
+impl FromStr for Rational {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Rational, Error> {
+        match s.split_once('/') {
+            Some((numer, denom)) => {
+                let numer = numer
+                    .trim()
+                    .parse::<i64>()
+                    .map_err(|e| Error::Conversion(format!("invalid rational {:?}: {}", s, e)))?;
+                let denom = denom
+                    .trim()
+                    .parse::<i64>()
+                    .map_err(|e| Error::Conversion(format!("invalid rational {:?}: {}", s, e)))?;
+                Ok(Rational::new(numer, denom))
+            }
+            None => s
+                .trim()
+                .parse::<i64>()
+                .map(|n| Rational::new(n, 1))
+                .map_err(|e| Error::Conversion(format!("invalid rational {:?}: {}", s, e))),
+        }
+    }
+}
+
+impl Serialize for Rational {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_str(&format!("{}/{}", self.0.numer(), self.0.denom()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Rational {
+    fn deserialize<D>(deserializer: D) -> Result<Rational, D::Error>
+        where D: Deserializer<'de>
+    {
+        String::deserialize(deserializer)
+            .and_then(|s| Rational::from_str(&s).map_err(serde::de::Error::custom))
+    }
+}
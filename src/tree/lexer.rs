@@ -0,0 +1,113 @@
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Ident(String),
+    Symbol(&'static str),
+}
+
+const TWO_CHAR_SYMBOLS: &[&str] = &["==", "!=", "<=", ">=", "&&", "||"];
+const ONE_CHAR_SYMBOLS: &[char] = &['+', '-', '*', '/', '%', '<', '>', '(', ')', ',', '!'];
+
+pub(crate) fn tokenize(expression: &str) -> Result<Vec<Token>, Error> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let start = i;
+            let mut is_float = false;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                if chars[i] == '.' {
+                    is_float = true;
+                }
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            if is_float {
+                let n = text
+                    .parse::<f64>()
+                    .map_err(|e| Error::Parse(format!("invalid number {:?}: {}", text, e)))?;
+                tokens.push(Token::Float(n));
+            } else {
+                let n = text
+                    .parse::<i64>()
+                    .map_err(|e| Error::Parse(format!("invalid number {:?}: {}", text, e)))?;
+                tokens.push(Token::Int(n));
+            }
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        if c == '"' || c == '\'' {
+            let quote = c;
+            i += 1;
+            let start = i;
+            while i < chars.len() && chars[i] != quote {
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err(Error::Parse(format!("unterminated string literal starting at {}", start)));
+            }
+            tokens.push(Token::Str(chars[start..i].iter().collect()));
+            i += 1;
+            continue;
+        }
+
+        if i + 1 < chars.len() {
+            let two: String = chars[i..i + 2].iter().collect();
+            if let Some(sym) = TWO_CHAR_SYMBOLS.iter().find(|s| **s == two) {
+                tokens.push(Token::Symbol(sym));
+                i += 2;
+                continue;
+            }
+        }
+
+        if let Some(sym) = ONE_CHAR_SYMBOLS.iter().find(|s| **s == c) {
+            tokens.push(Token::Symbol(symbol_str(*sym)));
+            i += 1;
+            continue;
+        }
+
+        return Err(Error::Parse(format!("unexpected character {:?} at position {}", c, i)));
+    }
+
+    Ok(tokens)
+}
+
+fn symbol_str(c: char) -> &'static str {
+    match c {
+        '+' => "+",
+        '-' => "-",
+        '*' => "*",
+        '/' => "/",
+        '%' => "%",
+        '<' => "<",
+        '>' => ">",
+        '(' => "(",
+        ')' => ")",
+        ',' => ",",
+        '!' => "!",
+        _ => unreachable!("symbol_str called with a character outside ONE_CHAR_SYMBOLS"),
+    }
+}
@@ -0,0 +1,227 @@
+
+mod lexer;
+mod parser;
+mod node;
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use node::Node;
+
+use crate::error::Error;
+use crate::{AsyncFunctions, Compiled, ConstFunctions, Context, Functions, NumericMode, Value};
+
+/// A parsed expression. `Tree::new` only stores the raw text; call
+/// `compile`/`compile_tree` to parse it into the node tree used for
+/// evaluation or introspection.
+pub struct Tree {
+    expression: String,
+    root: Option<Node>,
+}
+
+impl Tree {
+    /// Wrap an expression string. Nothing is parsed until `compile`/`compile_tree` runs.
+    pub fn new(expression: String) -> Tree {
+        Tree { expression, root: None }
+    }
+
+    /// Parse the expression, keeping the node tree around for introspection
+    /// (`identifiers`/`function_calls`). Idempotent: re-parses on every call,
+    /// which is what lets `Expr` compile on demand without caching a `Tree`.
+    pub fn compile_tree(mut self) -> Result<Tree, Error> {
+        self.root = Some(parser::parse(&self.expression)?);
+        Ok(self)
+    }
+
+    /// Parse the expression and produce a closure that evaluates it against
+    /// whatever contexts/functions/const_functions are passed in at call time.
+    pub fn compile(self) -> Result<Compiled, Error> {
+        let root = parser::parse(&self.expression)?;
+        Ok(Box::new(move |contexts: &[Context], functions: &Functions, const_functions: Rc<RefCell<ConstFunctions>>, numeric_mode: NumericMode| {
+            eval(&root, contexts, functions, &const_functions, numeric_mode)
+        }))
+    }
+
+    /// Parse and evaluate the expression, awaiting any async-function call.
+    /// A subtree that contains no async call is evaluated inline with the
+    /// same synchronous `eval` used by `compile`; only subtrees that do
+    /// reach an async function are walked with the (boxed, recursive) async
+    /// evaluator.
+    pub async fn exec_async(
+        self,
+        contexts: &[Context],
+        functions: &Functions,
+        const_functions: Rc<RefCell<ConstFunctions>>,
+        async_functions: &AsyncFunctions,
+        numeric_mode: NumericMode,
+    ) -> Result<Value, Error> {
+        let root = parser::parse(&self.expression)?;
+        let async_names: Vec<String> = async_functions.keys().cloned().collect();
+        eval_async(&root, contexts, functions, &const_functions, async_functions, &async_names, numeric_mode).await
+    }
+
+    /// Every variable/context key read by this (already-compiled) tree.
+    pub fn identifiers(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        if let Some(root) = &self.root {
+            root.walk_identifiers(&mut names);
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Every function name invoked by this (already-compiled) tree.
+    pub fn function_calls(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        if let Some(root) = &self.root {
+            root.walk_function_calls(&mut names);
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// Look up an identifier in `contexts`, searching the most recently pushed
+/// scope first so inner scopes shadow outer ones.
+fn lookup_identifier(name: &str, contexts: &[Context]) -> Result<Value, Error> {
+    contexts
+        .iter()
+        .rev()
+        .find_map(|context| context.get(name))
+        .cloned()
+        .ok_or_else(|| Error::UnknownIdentifier(name.to_string()))
+}
+
+/// Call a user function, falling back to a user const function, then to the
+/// built-in conversion/math/`min`/`max`/`len` functions (`crate::builtins`).
+/// User registrations are checked first so a caller can always shadow a
+/// built-in by registering a function of the same name. Each user-registered
+/// function is checked against its configured `min_args`/`max_args` before
+/// the closure runs, so a bad call fails with a named
+/// `Error::ArgumentsLess`/`Error::ArgumentsGreater` instead of whatever the
+/// closure itself does with too few/many values.
+fn call_function(
+    name: &str,
+    args: Vec<Value>,
+    functions: &Functions,
+    const_functions: &Rc<RefCell<ConstFunctions>>,
+) -> Result<Value, Error> {
+    if let Some(function) = functions.get(name) {
+        function.check_arity(name, &args)?;
+        return (function.compiled)(args);
+    }
+    if let Some(function) = const_functions.borrow().get(name) {
+        function.check_arity(name, &args)?;
+        return (function.compiled)(args);
+    }
+    if let Some(builtin) = crate::builtins::lookup(name) {
+        return builtin(args);
+    }
+    Err(Error::UnknownFunction(name.to_string()))
+}
+
+fn eval(
+    node: &Node,
+    contexts: &[Context],
+    functions: &Functions,
+    const_functions: &Rc<RefCell<ConstFunctions>>,
+    numeric_mode: NumericMode,
+) -> Result<Value, Error> {
+    match node {
+        Node::Null => Ok(Value::Null),
+        Node::Bool(b) => Ok(Value::Bool(*b)),
+        Node::Int(n) => Ok(Value::from(*n)),
+        Node::Float(n) => Ok(Value::from(*n)),
+        Node::Str(s) => Ok(Value::String(s.clone())),
+        Node::Identifier(name) => lookup_identifier(name, contexts),
+        Node::Call(name, arg_nodes) => {
+            let args = arg_nodes
+                .iter()
+                .map(|arg| eval(arg, contexts, functions, const_functions, numeric_mode))
+                .collect::<Result<Vec<Value>, Error>>()?;
+            call_function(name, args, functions, const_functions)
+        }
+        Node::Unary(op, operand) => {
+            let value = eval(operand, contexts, functions, const_functions, numeric_mode)?;
+            node::eval_unary(*op, value, numeric_mode)
+        }
+        Node::Binary(op, lhs, rhs) => {
+            let lhs = eval(lhs, contexts, functions, const_functions, numeric_mode)?;
+            let rhs = eval(rhs, contexts, functions, const_functions, numeric_mode)?;
+            node::eval_binary(*op, lhs, rhs, numeric_mode)
+        }
+    }
+}
+
+/// Boxed so it can recurse: an `async fn` can't directly call itself.
+/// Falls back to the plain synchronous `eval` for any subtree that doesn't
+/// reach an async function, so pure sub-expressions never pay for awaiting.
+fn eval_async<'a>(
+    node: &'a Node,
+    contexts: &'a [Context],
+    functions: &'a Functions,
+    const_functions: &'a Rc<RefCell<ConstFunctions>>,
+    async_functions: &'a AsyncFunctions,
+    async_names: &'a [String],
+    numeric_mode: NumericMode,
+) -> Pin<Box<dyn Future<Output = Result<Value, Error>> + 'a>> {
+    Box::pin(async move {
+        if !node.contains_call_to(async_names) {
+            return eval(node, contexts, functions, const_functions, numeric_mode);
+        }
+
+        match node {
+            Node::Call(name, arg_nodes) => {
+                let mut args = Vec::with_capacity(arg_nodes.len());
+                for arg in arg_nodes {
+                    args.push(eval_async(arg, contexts, functions, const_functions, async_functions, async_names, numeric_mode).await?);
+                }
+                if let Some(async_function) = async_functions.get(name) {
+                    async_function.check_arity(name, &args)?;
+                    return (async_function.compiled)(args).await;
+                }
+                call_function(name, args, functions, const_functions)
+            }
+            Node::Unary(op, operand) => {
+                let value = eval_async(operand, contexts, functions, const_functions, async_functions, async_names, numeric_mode).await?;
+                node::eval_unary(*op, value, numeric_mode)
+            }
+            Node::Binary(op, lhs, rhs) => {
+                let lhs_value = eval_async(lhs, contexts, functions, const_functions, async_functions, async_names, numeric_mode).await?;
+                let rhs_value = eval_async(rhs, contexts, functions, const_functions, async_functions, async_names, numeric_mode).await?;
+                node::eval_binary(*op, lhs_value, rhs_value, numeric_mode)
+            }
+            Node::Null | Node::Bool(_) | Node::Int(_) | Node::Float(_) | Node::Str(_) | Node::Identifier(_) => {
+                eval(node, contexts, functions, const_functions, numeric_mode)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifiers_collects_every_variable_read_deduped_and_sorted() {
+        let tree = Tree::new("b + a > a".to_string()).compile_tree().unwrap();
+        assert_eq!(tree.identifiers(), vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn function_calls_collects_every_call_deduped_and_sorted() {
+        let tree = Tree::new("max(a, b) + min(a, b)".to_string()).compile_tree().unwrap();
+        assert_eq!(tree.function_calls(), vec!["max".to_string(), "min".to_string()]);
+    }
+
+    #[test]
+    fn identifiers_and_function_calls_are_empty_for_a_tree_that_has_not_compiled() {
+        let tree = Tree::new("a + b".to_string());
+        assert!(tree.identifiers().is_empty());
+        assert!(tree.function_calls().is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+
+use num_traits::Zero;
+
+use crate::error::Error;
+use crate::rational::Rational;
+use crate::{NumericMode, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum UnaryOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Identifier(String),
+    Call(String, Vec<Node>),
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+}
+
+impl Node {
+    pub(crate) fn walk_identifiers(&self, out: &mut Vec<String>) {
+        match self {
+            Node::Identifier(name) => out.push(name.clone()),
+            Node::Call(_, args) => args.iter().for_each(|arg| arg.walk_identifiers(out)),
+            Node::Unary(_, operand) => operand.walk_identifiers(out),
+            Node::Binary(_, lhs, rhs) => {
+                lhs.walk_identifiers(out);
+                rhs.walk_identifiers(out);
+            }
+            Node::Null | Node::Bool(_) | Node::Int(_) | Node::Float(_) | Node::Str(_) => {}
+        }
+    }
+
+    pub(crate) fn walk_function_calls(&self, out: &mut Vec<String>) {
+        match self {
+            Node::Call(name, args) => {
+                out.push(name.clone());
+                args.iter().for_each(|arg| arg.walk_function_calls(out));
+            }
+            Node::Unary(_, operand) => operand.walk_function_calls(out),
+            Node::Binary(_, lhs, rhs) => {
+                lhs.walk_function_calls(out);
+                rhs.walk_function_calls(out);
+            }
+            Node::Null | Node::Bool(_) | Node::Int(_) | Node::Float(_) | Node::Str(_) | Node::Identifier(_) => {}
+        }
+    }
+
+    /// Whether this node or anything in its subtree calls one of `names`.
+    /// Used to decide whether a subtree needs the async evaluator at all.
+    pub(crate) fn contains_call_to(&self, names: &[String]) -> bool {
+        match self {
+            Node::Call(name, args) => {
+                names.iter().any(|n| n == name) || args.iter().any(|arg| arg.contains_call_to(names))
+            }
+            Node::Unary(_, operand) => operand.contains_call_to(names),
+            Node::Binary(_, lhs, rhs) => lhs.contains_call_to(names) || rhs.contains_call_to(names),
+            Node::Null | Node::Bool(_) | Node::Int(_) | Node::Float(_) | Node::Str(_) | Node::Identifier(_) => false,
+        }
+    }
+}
+
+pub(crate) fn eval_unary(op: UnaryOp, value: Value, numeric_mode: NumericMode) -> Result<Value, Error> {
+    match op {
+        UnaryOp::Not => value
+            .as_bool()
+            .map(|b| Value::Bool(!b))
+            .ok_or_else(|| Error::Conversion(format!("`!` expects a boolean, got {:?}", value))),
+        UnaryOp::Neg => match numeric_mode {
+            NumericMode::Float => value
+                .as_f64()
+                .map(|n| Value::from(-n))
+                .ok_or_else(|| Error::Conversion(format!("unary `-` expects a number, got {:?}", value))),
+            NumericMode::Rational => Rational::from_value(&value).map(|r| Rational(-r.0).to_value()),
+        },
+    }
+}
+
+pub(crate) fn eval_binary(op: BinaryOp, lhs: Value, rhs: Value, numeric_mode: NumericMode) -> Result<Value, Error> {
+    match op {
+        BinaryOp::And => Ok(Value::Bool(as_bool(&lhs)? && as_bool(&rhs)?)),
+        BinaryOp::Or => Ok(Value::Bool(as_bool(&lhs)? || as_bool(&rhs)?)),
+        BinaryOp::Eq => Ok(Value::Bool(lhs == rhs)),
+        BinaryOp::Ne => Ok(Value::Bool(lhs != rhs)),
+        _ => match numeric_mode {
+            NumericMode::Float => eval_float_binary(op, lhs, rhs),
+            NumericMode::Rational => eval_rational_binary(op, lhs, rhs),
+        },
+    }
+}
+
+fn eval_float_binary(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, Error> {
+    match op {
+        BinaryOp::Lt => Ok(Value::Bool(as_f64(&lhs)? < as_f64(&rhs)?)),
+        BinaryOp::Le => Ok(Value::Bool(as_f64(&lhs)? <= as_f64(&rhs)?)),
+        BinaryOp::Gt => Ok(Value::Bool(as_f64(&lhs)? > as_f64(&rhs)?)),
+        BinaryOp::Ge => Ok(Value::Bool(as_f64(&lhs)? >= as_f64(&rhs)?)),
+        BinaryOp::Add => finite(as_f64(&lhs)? + as_f64(&rhs)?),
+        BinaryOp::Sub => finite(as_f64(&lhs)? - as_f64(&rhs)?),
+        BinaryOp::Mul => finite(as_f64(&lhs)? * as_f64(&rhs)?),
+        BinaryOp::Div => finite(as_f64(&lhs)? / as_f64(&rhs)?),
+        BinaryOp::Rem => finite(as_f64(&lhs)? % as_f64(&rhs)?),
+        BinaryOp::And | BinaryOp::Or | BinaryOp::Eq | BinaryOp::Ne => unreachable!("handled in eval_binary"),
+    }
+}
+
+/// Exact arithmetic for `NumericMode::Rational`: both operands are parsed as
+/// `Rational` (accepting the `"n/d"` string form or a bare integer `Value`)
+/// so `1/3 + 1/6` doesn't suffer the float drift `eval_float_binary` would introduce.
+fn eval_rational_binary(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, Error> {
+    let lhs = Rational::from_value(&lhs)?;
+    let rhs = Rational::from_value(&rhs)?;
+    if matches!(op, BinaryOp::Div | BinaryOp::Rem) && rhs.0.numer().is_zero() {
+        return Err(Error::Conversion("division by zero".to_string()));
+    }
+    match op {
+        BinaryOp::Lt => Ok(Value::Bool(lhs.0 < rhs.0)),
+        BinaryOp::Le => Ok(Value::Bool(lhs.0 <= rhs.0)),
+        BinaryOp::Gt => Ok(Value::Bool(lhs.0 > rhs.0)),
+        BinaryOp::Ge => Ok(Value::Bool(lhs.0 >= rhs.0)),
+        BinaryOp::Add => Ok(Rational(lhs.0 + rhs.0).to_value()),
+        BinaryOp::Sub => Ok(Rational(lhs.0 - rhs.0).to_value()),
+        BinaryOp::Mul => Ok(Rational(lhs.0 * rhs.0).to_value()),
+        BinaryOp::Div => Ok(Rational(lhs.0 / rhs.0).to_value()),
+        BinaryOp::Rem => Ok(Rational(lhs.0 % rhs.0).to_value()),
+        BinaryOp::And | BinaryOp::Or | BinaryOp::Eq | BinaryOp::Ne => unreachable!("handled in eval_binary"),
+    }
+}
+
+/// Reject `NaN`/infinite results instead of letting `Value::from(f64)` coerce
+/// them to `Value::Null` (e.g. `1 / 0`), which would otherwise make a domain
+/// error evaluate to a silently-wrong `null` instead of failing.
+pub(crate) fn finite(n: f64) -> Result<Value, Error> {
+    if n.is_finite() {
+        Ok(Value::from(n))
+    } else {
+        Err(Error::Conversion(format!("operation produced a non-finite result ({})", n)))
+    }
+}
+
+fn as_bool(value: &Value) -> Result<bool, Error> {
+    value
+        .as_bool()
+        .ok_or_else(|| Error::Conversion(format!("expected a boolean, got {:?}", value)))
+}
+
+fn as_f64(value: &Value) -> Result<f64, Error> {
+    value
+        .as_f64()
+        .ok_or_else(|| Error::Conversion(format!("expected a number, got {:?}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_mode_uses_f64_arithmetic() {
+        let sum = eval_binary(BinaryOp::Add, Value::from(1), Value::from(3), NumericMode::Float).unwrap();
+        assert_eq!(sum, Value::from(4.0));
+    }
+
+    #[test]
+    fn rational_mode_avoids_float_drift() {
+        let lhs = Rational::new(1, 3).to_value();
+        let rhs = Rational::new(1, 6).to_value();
+        let sum = eval_binary(BinaryOp::Add, lhs, rhs, NumericMode::Rational).unwrap();
+        assert_eq!(sum, Value::String("1/2".to_string()));
+    }
+
+    #[test]
+    fn rational_mode_compares_exactly() {
+        let lhs = Rational::new(1, 3).to_value();
+        let rhs = Rational::new(1, 2).to_value();
+        let lt = eval_binary(BinaryOp::Lt, lhs, rhs, NumericMode::Rational).unwrap();
+        assert_eq!(lt, Value::Bool(true));
+    }
+
+    #[test]
+    fn rational_mode_negates() {
+        let value = Rational::new(1, 3).to_value();
+        let negated = eval_unary(UnaryOp::Neg, value, NumericMode::Rational).unwrap();
+        assert_eq!(negated, Value::String("-1/3".to_string()));
+    }
+
+    #[test]
+    fn rational_mode_division_by_zero_errors_instead_of_panicking() {
+        let lhs = Rational::new(1, 2).to_value();
+        let rhs = Rational::new(0, 1).to_value();
+        assert!(eval_binary(BinaryOp::Div, lhs.clone(), rhs.clone(), NumericMode::Rational).is_err());
+        assert!(eval_binary(BinaryOp::Rem, lhs, rhs, NumericMode::Rational).is_err());
+    }
+
+    #[test]
+    fn float_mode_division_by_zero_errors_instead_of_returning_null() {
+        let result = eval_binary(BinaryOp::Div, Value::from(1), Value::from(0), NumericMode::Float);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn float_mode_rejects_non_finite_results() {
+        // `0.0 / 0.0` is NaN, which `Value::from(f64)` would otherwise silently coerce to `Value::Null`.
+        let result = eval_binary(BinaryOp::Div, Value::from(0.0), Value::from(0.0), NumericMode::Float);
+        assert!(result.is_err());
+    }
+}
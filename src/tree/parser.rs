@@ -0,0 +1,199 @@
+
+use crate::error::Error;
+
+use super::lexer::{self, Token};
+use super::node::{BinaryOp, Node, UnaryOp};
+
+/// Parse a full expression string into a `Node` tree.
+pub(crate) fn parse(expression: &str) -> Result<Node, Error> {
+    let tokens = lexer::tokenize(expression)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let node = parser.parse_or()?;
+    if parser.pos != parser.tokens.len() {
+        return Err(Error::Parse(format!(
+            "unexpected trailing token at position {}",
+            parser.pos
+        )));
+    }
+    Ok(node)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn bump(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), Error> {
+        match self.bump() {
+            Some(Token::Symbol(s)) if s == symbol => Ok(()),
+            other => Err(Error::Parse(format!("expected {:?}, got {:?}", symbol, other))),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Node, Error> {
+        let mut node = self.parse_and()?;
+        while self.peek_symbol("||") {
+            self.pos += 1;
+            let rhs = self.parse_and()?;
+            node = Node::Binary(BinaryOp::Or, Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, Error> {
+        let mut node = self.parse_equality()?;
+        while self.peek_symbol("&&") {
+            self.pos += 1;
+            let rhs = self.parse_equality()?;
+            node = Node::Binary(BinaryOp::And, Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    fn parse_equality(&mut self) -> Result<Node, Error> {
+        let mut node = self.parse_relational()?;
+        loop {
+            let op = if self.peek_symbol("==") {
+                BinaryOp::Eq
+            } else if self.peek_symbol("!=") {
+                BinaryOp::Ne
+            } else {
+                break;
+            };
+            self.pos += 1;
+            let rhs = self.parse_relational()?;
+            node = Node::Binary(op, Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    fn parse_relational(&mut self) -> Result<Node, Error> {
+        let mut node = self.parse_additive()?;
+        loop {
+            let op = if self.peek_symbol("<=") {
+                BinaryOp::Le
+            } else if self.peek_symbol(">=") {
+                BinaryOp::Ge
+            } else if self.peek_symbol("<") {
+                BinaryOp::Lt
+            } else if self.peek_symbol(">") {
+                BinaryOp::Gt
+            } else {
+                break;
+            };
+            self.pos += 1;
+            let rhs = self.parse_additive()?;
+            node = Node::Binary(op, Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    fn parse_additive(&mut self) -> Result<Node, Error> {
+        let mut node = self.parse_multiplicative()?;
+        loop {
+            let op = if self.peek_symbol("+") {
+                BinaryOp::Add
+            } else if self.peek_symbol("-") {
+                BinaryOp::Sub
+            } else {
+                break;
+            };
+            self.pos += 1;
+            let rhs = self.parse_multiplicative()?;
+            node = Node::Binary(op, Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Node, Error> {
+        let mut node = self.parse_unary()?;
+        loop {
+            let op = if self.peek_symbol("*") {
+                BinaryOp::Mul
+            } else if self.peek_symbol("/") {
+                BinaryOp::Div
+            } else if self.peek_symbol("%") {
+                BinaryOp::Rem
+            } else {
+                break;
+            };
+            self.pos += 1;
+            let rhs = self.parse_unary()?;
+            node = Node::Binary(op, Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, Error> {
+        if self.peek_symbol("!") {
+            self.pos += 1;
+            return Ok(Node::Unary(UnaryOp::Not, Box::new(self.parse_unary()?)));
+        }
+        if self.peek_symbol("-") {
+            self.pos += 1;
+            return Ok(Node::Unary(UnaryOp::Neg, Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, Error> {
+        match self.bump() {
+            Some(Token::Int(n)) => Ok(Node::Int(n)),
+            Some(Token::Float(n)) => Ok(Node::Float(n)),
+            Some(Token::Str(s)) => Ok(Node::Str(s)),
+            Some(Token::Ident(name)) => {
+                if self.peek_symbol("(") {
+                    self.pos += 1;
+                    let args = self.parse_call_args()?;
+                    return Ok(Node::Call(name, args));
+                }
+                match name.as_str() {
+                    "true" => Ok(Node::Bool(true)),
+                    "false" => Ok(Node::Bool(false)),
+                    "null" => Ok(Node::Null),
+                    _ => Ok(Node::Identifier(name)),
+                }
+            }
+            Some(Token::Symbol("(")) => {
+                let node = self.parse_or()?;
+                self.expect_symbol(")")?;
+                Ok(node)
+            }
+            other => Err(Error::Parse(format!("unexpected token {:?}", other))),
+        }
+    }
+
+    fn parse_call_args(&mut self) -> Result<Vec<Node>, Error> {
+        let mut args = Vec::new();
+        if self.peek_symbol(")") {
+            self.pos += 1;
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_or()?);
+            match self.bump() {
+                Some(Token::Symbol(",")) => continue,
+                Some(Token::Symbol(")")) => break,
+                other => return Err(Error::Parse(format!("expected ',' or ')' in call, got {:?}", other))),
+            }
+        }
+        Ok(args)
+    }
+}